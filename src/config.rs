@@ -0,0 +1,103 @@
+use std::fs;
+use std::path::PathBuf;
+
+/// Shell configuration loaded from `~/.rushrc` at startup. Missing or
+/// malformed config falls back to these defaults rather than aborting
+/// startup.
+pub struct Config {
+    pub prompt: String,
+    pub multiline_prompt: bool,
+    pub history_limit: usize,
+    pub show_errors: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            prompt: "$ ".to_string(),
+            multiline_prompt: false,
+            history_limit: 1000,
+            show_errors: true,
+        }
+    }
+}
+
+impl Config {
+    /// Reads `~/.rushrc`, parsing simple `key: value` lines. Any error
+    /// (missing file, bad permissions, garbage lines) just falls back to
+    /// the default for that key rather than failing startup.
+    pub fn load() -> Self {
+        let mut config = Self::default();
+        let Some(path) = std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".rushrc")) else {
+            return config;
+        };
+        let Ok(contents) = fs::read_to_string(path) else {
+            return config;
+        };
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "prompt" => config.prompt = value.to_string(),
+                "multiline-prompt" => {
+                    if let Some(flag) = parse_bool(value) {
+                        config.multiline_prompt = flag;
+                    }
+                }
+                "history-limit" => {
+                    if let Ok(limit) = value.parse() {
+                        config.history_limit = limit;
+                    }
+                }
+                "show-errors" => {
+                    if let Some(flag) = parse_bool(value) {
+                        config.show_errors = flag;
+                    }
+                }
+                _ => {}
+            }
+        }
+        config
+    }
+
+    /// Expands `\w` (current directory) and `\h` (hostname) in the prompt
+    /// template, appending a newline first when `multiline-prompt` is set.
+    pub fn render_prompt(&self) -> String {
+        let cwd = std::env::current_dir()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+        let rendered = self
+            .prompt
+            .replace("\\w", &cwd)
+            .replace("\\h", &hostname());
+        if self.multiline_prompt {
+            format!("\n{}", rendered)
+        } else {
+            rendered
+        }
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "1" => Some(true),
+        "false" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+fn hostname() -> String {
+    let mut buf = [0u8; 256];
+    let ok = unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) == 0 };
+    if !ok {
+        return String::new();
+    }
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..len]).to_string()
+}
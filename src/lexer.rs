@@ -0,0 +1,193 @@
+use anyhow::{bail, Result};
+
+/// Reads a `$NAME`, `${NAME}` or `$?` reference (the `$` itself already
+/// consumed) and resolves it: `$?` expands to the last command's exit
+/// code, everything else is looked up against the process environment and
+/// expands to an empty string if unset.
+fn expand_var(
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+    last_exit_code: i32,
+) -> Result<String> {
+    if chars.peek() == Some(&'?') {
+        chars.next();
+        return Ok(last_exit_code.to_string());
+    }
+    let name = if chars.peek() == Some(&'{') {
+        chars.next();
+        let mut name = String::new();
+        loop {
+            match chars.next() {
+                Some('}') => break,
+                Some(ch) => name.push(ch),
+                None => bail!("unterminated variable reference"),
+            }
+        }
+        name
+    } else {
+        let mut name = String::new();
+        while let Some(&ch) = chars.peek() {
+            if ch.is_alphanumeric() || ch == '_' {
+                name.push(ch);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        name
+    };
+    Ok(std::env::var(&name).unwrap_or_default())
+}
+
+/// Tokenizes a line of shell input into its constituent arguments.
+///
+/// Single quotes are literal (no escapes, no `$` expansion recognized
+/// inside them); double quotes group whitespace but still recognize
+/// backslash escapes for `"`, `\`, `$` and newline (matching POSIX, a
+/// backslash before anything else inside double quotes is kept literally)
+/// and still expand `$VAR`/`${VAR}`. Outside quotes, a backslash escapes
+/// the following character and `$VAR`/`${VAR}` are expanded the same way.
+/// `|`, `<`, `&` and `>`/`>>` are split off as their own tokens even
+/// without surrounding whitespace, since pipeline, redirection and
+/// background-job parsing downstream works on token boundaries rather
+/// than raw characters. An unterminated quote is an error rather than
+/// silently spanning to the next line. `last_exit_code` is substituted for
+/// `$?`.
+pub fn tokenize(input: &str, last_exit_code: i32) -> Result<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' | '\n' | '\r' => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            '|' | '<' | '&' => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+                tokens.push(c.to_string());
+            }
+            '>' => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+                if chars.peek() == Some(&'>') {
+                    chars.next();
+                    tokens.push(">>".to_string());
+                } else {
+                    tokens.push(">".to_string());
+                }
+            }
+            '\'' => {
+                in_token = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(ch) => current.push(ch),
+                        None => bail!("unterminated single quote"),
+                    }
+                }
+            }
+            '"' => {
+                in_token = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.peek() {
+                            Some('"') | Some('\\') | Some('$') | Some('\n') => {
+                                current.push(chars.next().expect("peeked Some"));
+                            }
+                            _ => current.push('\\'),
+                        },
+                        Some('$') => current.push_str(&expand_var(&mut chars, last_exit_code)?),
+                        Some(ch) => current.push(ch),
+                        None => bail!("unterminated double quote"),
+                    }
+                }
+            }
+            '\\' => {
+                in_token = true;
+                match chars.next() {
+                    Some(ch) => current.push(ch),
+                    None => bail!("trailing backslash with no escaped character"),
+                }
+            }
+            '$' => {
+                in_token = true;
+                current.push_str(&expand_var(&mut chars, last_exit_code)?);
+            }
+            other => {
+                in_token = true;
+                current.push(other);
+            }
+        }
+    }
+    if in_token {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_on_whitespace() {
+        assert_eq!(tokenize("echo  hi  there", 0).unwrap(), vec!["echo", "hi", "there"]);
+    }
+
+    #[test]
+    fn single_quotes_are_literal() {
+        assert_eq!(
+            tokenize("echo 'a $HOME b'", 0).unwrap(),
+            vec!["echo", "a $HOME b"]
+        );
+    }
+
+    #[test]
+    fn double_quotes_recognize_escapes_and_expansion() {
+        std::env::set_var("LEXER_TEST_VAR", "val");
+        assert_eq!(
+            tokenize(r#"echo "a \"quoted\" $LEXER_TEST_VAR \$literal \z""#, 0).unwrap(),
+            vec!["echo", "a \"quoted\" val $literal \\z"]
+        );
+    }
+
+    #[test]
+    fn operators_split_mid_word_without_whitespace() {
+        assert_eq!(
+            tokenize("cat<in|grep foo>out>>log&", 0).unwrap(),
+            vec![
+                "cat", "<", "in", "|", "grep", "foo", ">", "out", ">>", "log", "&"
+            ]
+        );
+    }
+
+    #[test]
+    fn question_mark_expands_to_last_exit_code() {
+        assert_eq!(tokenize("echo $?", 42).unwrap(), vec!["echo", "42"]);
+    }
+
+    #[test]
+    fn unterminated_single_quote_is_an_error() {
+        assert!(tokenize("echo 'unterminated", 0).is_err());
+    }
+
+    #[test]
+    fn unterminated_double_quote_is_an_error() {
+        assert!(tokenize("echo \"unterminated", 0).is_err());
+    }
+
+    #[test]
+    fn unterminated_variable_reference_is_an_error() {
+        assert!(tokenize("echo ${unterminated", 0).is_err());
+    }
+}
@@ -1,64 +1,116 @@
+mod config;
+mod lexer;
+mod readline;
+
 use anyhow::{Context, Result};
 use std::env;
-use std::fs;
+use std::fs::{self, File, OpenOptions};
 #[allow(unused_imports)]
 use std::io::{self, Write};
 use std::os::unix::fs::PermissionsExt;
 use std::path::Path;
+use std::process::Stdio;
 use std::{
-    io::{Stderr, Stdin, Stdout},
+    io::{Stderr, Stdout},
     path::PathBuf,
     process::exit,
 };
 
 fn main() {
-    let mut shell = Shell::new();
-    let path = match env::var("PATH") {
+    let path: Vec<PathBuf> = match env::var("PATH") {
         Ok(p) => p.split(':').map(PathBuf::from).collect(),
         Err(_) => Vec::default(),
     };
+    let mut shell = Shell::new();
     loop {
-        let input = shell.read_stdin().unwrap();
-        let output = parse(input, &path);
+        shell.reap_jobs();
+        let Some(input) = shell.read_stdin(&path).unwrap() else {
+            break;
+        };
+        let output = parse(input, &path, &mut shell);
         match output {
             Ok(out) => {
                 if !out.is_empty() {
                     let _ = shell.write_stdout(out.to_string());
                 };
             }
-            Err(e) => shell.write_stderr(e.to_string()).unwrap(),
+            Err(e) => {
+                if shell.config.show_errors {
+                    shell.write_stderr(e.to_string()).unwrap();
+                }
+            }
         }
     }
 }
 
+/// A backgrounded pipeline, tracked by a monotonically increasing job id so
+/// `jobs`/`fg` can refer back to it. Every stage's `Child` is kept (not just
+/// the last one) so `reap_jobs`/`fg` wait on all of them and none are left
+/// as zombies.
+struct Job {
+    id: usize,
+    command: String,
+    children: Vec<std::process::Child>,
+}
+
 struct Shell {
-    stdin: Stdin,
     stdout: Stdout,
     stderr: Stderr,
+    readline: readline::Readline,
+    jobs: Vec<Job>,
+    next_job_id: usize,
+    config: config::Config,
+    last_exit_code: i32,
 }
 
 impl Shell {
     pub fn new() -> Self {
         io::stdout().flush().unwrap();
-        let stdin = io::stdin();
         let stdout = io::stdout();
         let stderr = io::stderr();
+        let config = config::Config::load();
+        let readline = readline::Readline::new(config.history_limit);
         Self {
-            stdin,
             stdout,
             stderr,
+            readline,
+            jobs: Vec::new(),
+            next_job_id: 1,
+            config,
+            last_exit_code: 0,
         }
     }
-    pub fn read_stdin(&mut self) -> Result<String> {
-        self.stdout
-            .write("$ ".as_bytes())
-            .context("failed writing shell prompt to stdout.")?;
-        self.stdout.flush().context("Failed to flush stdout")?;
-        let mut input = String::new();
-        self.stdin
-            .read_line(&mut input)
-            .context("Failed reading from stdin.")?;
-        Ok(input)
+    /// Reaps finished background jobs, printing a `[id]+ Done` notice once
+    /// every stage of the pipeline has exited. Called once per prompt.
+    pub fn reap_jobs(&mut self) {
+        let mut done = Vec::new();
+        self.jobs.retain_mut(|job| {
+            let all_exited = job
+                .children
+                .iter_mut()
+                .all(|child| matches!(child.try_wait(), Ok(Some(_))));
+            if all_exited {
+                done.push(format!("[{}]+ Done\t{}", job.id, job.command));
+                false
+            } else {
+                true
+            }
+        });
+        for message in done {
+            let _ = self.write_stdout(message);
+        }
+    }
+    /// Reads one line of input, returning `None` on Ctrl-D/EOF so the
+    /// caller can exit the REPL loop.
+    pub fn read_stdin(&mut self, path: &[PathBuf]) -> Result<Option<String>> {
+        let prompt = self.config.render_prompt();
+        match self
+            .readline
+            .read_line(&prompt, |prefix| build_completions(prefix, path))?
+        {
+            readline::LineResult::Submitted(line) => Ok(Some(line)),
+            readline::LineResult::Eof => Ok(None),
+        }
     }
     pub fn write_stdout(&mut self, text: String) -> Result<()> {
         writeln!(self.stdout, "{}", text)
@@ -67,7 +119,7 @@ impl Shell {
         Ok(())
     }
     pub fn write_stderr(&mut self, text: String) -> Result<()> {
-        writeln!(self.stdout, "{}", text)
+        writeln!(self.stderr, "{}", text)
             .with_context(|| format!("Failed writing message: '{}' to stderr.", text))?;
         Ok(())
     }
@@ -88,6 +140,9 @@ enum Command {
     Exit,
     Pwd,
     Cd,
+    Export,
+    Jobs,
+    Fg,
 }
 
 impl Command {
@@ -98,6 +153,9 @@ impl Command {
             "exit" => "exit is a shell builtin",
             "pwd" => "pwd is a shell builtin",
             "cd" => "cd is a shell builtin",
+            "export" => "export is a shell builtin",
+            "jobs" => "jobs is a shell builtin",
+            "fg" => "fg is a shell builtin",
             _ => return None,
         };
         Some(answer.to_string())
@@ -105,27 +163,145 @@ impl Command {
     pub fn get_command_path(command: &str, paths: &[PathBuf]) -> Option<String> {
         paths.iter().find_map(|p| {
             let full_path = p.join(command);
-            if (full_path.is_file() && is_executable(&full_path)) {
+            if full_path.is_file() && is_executable(&full_path) {
                 Some(full_path.to_str()?.to_string())
             } else {
                 None
             }
         })
     }
+    /// Lists every executable in `paths` whose name starts with `prefix`,
+    /// reusing `get_command_path`'s directory-walk approach for completion.
+    pub fn list_external(prefix: &str, paths: &[PathBuf]) -> Vec<String> {
+        paths
+            .iter()
+            .filter_map(|dir| fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let path = entry.path();
+                let name = path.file_name()?.to_str()?.to_string();
+                (name.starts_with(prefix) && is_executable(&path)).then_some(name)
+            })
+            .collect()
+    }
 }
 
-fn parse(input: String, path: &[PathBuf]) -> Result<String> {
-    let mut args = input.split_whitespace();
-    let command = args.next();
-    if command.is_none() {
-        return Ok(String::default());
+/// Completes filesystem paths: the prefix up to the last `/` is the
+/// directory to scan, the remainder is matched against entry names.
+fn complete_path(prefix: &str) -> Vec<String> {
+    let (dir, file_prefix) = match prefix.rfind('/') {
+        Some(idx) => (&prefix[..=idx], &prefix[idx + 1..]),
+        None => ("", prefix),
+    };
+    let Ok(entries) = fs::read_dir(if dir.is_empty() { "." } else { dir }) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            if !name.starts_with(file_prefix) {
+                return None;
+            }
+            Some(format!("{}{}", dir, name))
+        })
+        .collect()
+}
+
+/// Builds Tab-completion candidates for `prefix`: builtin names, external
+/// commands on `path`, and filesystem entries.
+fn build_completions(prefix: &str, path: &[PathBuf]) -> Vec<String> {
+    let mut candidates: Vec<String> = ["echo", "type", "exit", "pwd", "cd", "export", "jobs", "fg"]
+        .into_iter()
+        .filter(|builtin| builtin.starts_with(prefix))
+        .map(str::to_string)
+        .collect();
+    candidates.extend(Command::list_external(prefix, path));
+    candidates.extend(complete_path(prefix));
+    candidates.sort();
+    candidates.dedup();
+    candidates
+}
+
+/// One stage of a pipeline: the argv for that stage plus any redirection
+/// targets and leading `NAME=value` assignments parsed out of it. A stage's
+/// own `stdin_redirect`/`stdout_redirect` take precedence over the pipe
+/// connecting it to its neighbours, whichever stage it is.
+struct Segment {
+    args: Vec<String>,
+    env_vars: Vec<(String, String)>,
+    stdin_redirect: Option<PathBuf>,
+    stdout_redirect: Option<(PathBuf, bool)>,
+}
+
+/// Parses a `NAME=value` token, requiring `NAME` to be a valid identifier
+/// (shells don't treat e.g. `1FOO=bar` as an assignment).
+fn parse_assignment(token: &str) -> Option<(String, String)> {
+    let (name, value) = token.split_once('=')?;
+    let mut chars = name.chars();
+    let starts_ident = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_');
+    if starts_ident && chars.all(|c| c.is_alphanumeric() || c == '_') {
+        Some((name.to_string(), value.to_string()))
+    } else {
+        None
     }
-    let command = command.expect("Command was checked for none right beforehand.");
+}
+
+/// Extracts a segment's argv and any `<`, `>`, `>>` targets from its tokens,
+/// then peels off any leading `NAME=value` assignments that precede the
+/// command word.
+fn parse_segment(tokens: &[String]) -> Segment {
+    let mut args = Vec::new();
+    let mut stdin_redirect = None;
+    let mut stdout_redirect = None;
+    let mut tokens = tokens.iter();
+    while let Some(token) = tokens.next() {
+        match token.as_str() {
+            "<" => stdin_redirect = tokens.next().map(PathBuf::from),
+            ">" => stdout_redirect = tokens.next().map(|p| (PathBuf::from(p), false)),
+            ">>" => stdout_redirect = tokens.next().map(|p| (PathBuf::from(p), true)),
+            _ => args.push(token.clone()),
+        }
+    }
+
+    let assignment_count = args
+        .iter()
+        .take_while(|arg| parse_assignment(arg).is_some())
+        .count();
+    let env_vars = args
+        .drain(..assignment_count)
+        .map(|arg| parse_assignment(&arg).expect("filtered by take_while above"))
+        .collect();
+
+    Segment {
+        args,
+        env_vars,
+        stdin_redirect,
+        stdout_redirect,
+    }
+}
+
+/// Splits a tokenized line into pipeline segments on `|` and parses each one.
+fn parse_segments(tokens: &[String]) -> Vec<Segment> {
+    tokens
+        .split(|token| token == "|")
+        .map(parse_segment)
+        .collect()
+}
 
+/// Runs a single builtin in-process. Returns `None` if `command` isn't a
+/// builtin so the caller can fall back to spawning an external program.
+fn run_builtin(
+    command: &str,
+    args: &[String],
+    path: &[PathBuf],
+    shell: &mut Shell,
+) -> Option<Result<String>> {
     let response = match command {
-        "echo" => args.collect::<Vec<&str>>().join(" "),
+        "echo" => args.join(" "),
         "exit" => exit(0),
-        "type" => args.next().map_or_else(
+        "type" => args.first().map_or_else(
             || "type: expected an argument of a command name".to_string(),
             |cmd| {
                 if let Some(builtin) = Command::is_builtin(cmd) {
@@ -140,13 +316,13 @@ fn parse(input: String, path: &[PathBuf]) -> Result<String> {
         "pwd" => std::env::current_dir().unwrap().display().to_string(),
         "cd" => {
             let target_path = args
-                .next()
+                .first()
                 .map(|p| p.replace('~', &env::var("HOME").unwrap()))
                 .unwrap_or_else(|| env::var("HOME").unwrap_or_default());
             let path_buf = PathBuf::from(target_path);
 
             if path_buf.as_os_str().is_empty() {
-                return Ok("cd: HOME environment variable not set".to_string());
+                return Some(Ok("cd: HOME environment variable not set".to_string()));
             }
 
             Shell::change_dir(&path_buf).map_or_else(
@@ -162,22 +338,269 @@ fn parse(input: String, path: &[PathBuf]) -> Result<String> {
                 |_| String::default(),
             )
         }
-
-        otherwise => Command::get_command_path(otherwise, path)
-            .map(|path| {
-                let output = std::process::Command::new(path)
-                    .args(args)
-                    .output()
-                    .expect("Failed to execute command");
-                String::from_utf8_lossy(&output.stdout)
-                    .to_string()
-                    .trim_end()
-                    .to_string()
-            })
-            .unwrap_or_else(|| format!("{}: command not found", &command)),
+        "export" => {
+            for arg in args {
+                match parse_assignment(arg) {
+                    Some((name, value)) => env::set_var(name, value),
+                    None => {
+                        return Some(Err(anyhow::anyhow!(
+                            "export: not a valid identifier: {}",
+                            arg
+                        )))
+                    }
+                }
+            }
+            String::default()
+        }
+        "jobs" => shell
+            .jobs
+            .iter()
+            .map(|job| format!("[{}]  Running\t{}", job.id, job.command))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        "fg" => {
+            let requested_id = args.first().and_then(|arg| arg.parse::<usize>().ok());
+            let index = match requested_id {
+                Some(id) => shell.jobs.iter().position(|job| job.id == id),
+                None => shell.jobs.len().checked_sub(1),
+            };
+            let Some(index) = index else {
+                return Some(Ok("fg: no such job".to_string()));
+            };
+            let mut job = shell.jobs.remove(index);
+            let last_index = job.children.len().saturating_sub(1);
+            for (i, child) in job.children.iter_mut().enumerate() {
+                match child.wait() {
+                    Ok(status) => {
+                        if i == last_index {
+                            shell.last_exit_code = status.code().unwrap_or(1);
+                        }
+                    }
+                    Err(e) => {
+                        return Some(Err(anyhow::anyhow!(
+                            "fg: failed to wait on job {}: {}",
+                            job.id,
+                            e
+                        )))
+                    }
+                }
+            }
+            String::default()
+        }
+        _ => return None,
     };
+    Some(Ok(response))
+}
 
-    Ok(response)
+/// Opens the redirect target for a stage's stdout, truncating or appending
+/// as requested.
+fn open_stdout_redirect(target: &Path, append: bool) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(target)
+        .with_context(|| format!("Failed to open {} for writing", target.display()))
+}
+
+/// Runs a (possibly single-stage) pipeline, wiring each stage's stdout to
+/// the next stage's stdin, honouring `<`/`>`/`>>` redirection on the first
+/// and last stages, and letting builtins participate by writing their
+/// output straight into whatever the next stage expects as input. If
+/// `background` is set, the pipeline is registered as a job and control
+/// returns to the prompt immediately instead of waiting on it.
+fn execute_pipeline(
+    segments: Vec<Segment>,
+    path: &[PathBuf],
+    shell: &mut Shell,
+    background: bool,
+    label: String,
+) -> Result<String> {
+    let last_index = segments.len().saturating_sub(1);
+    let mut children: Vec<(String, std::process::Child)> = Vec::new();
+    let mut prev_stdout: Option<std::process::ChildStdout> = None;
+    let mut builtin_feed: Option<String> = None;
+    let mut captured = String::default();
+
+    for (i, segment) in segments.into_iter().enumerate() {
+        let Some(command) = segment.args.first().cloned() else {
+            // A bare `NAME=value` with no command word sets a shell-level
+            // variable rather than scoping it to a child.
+            for (name, value) in &segment.env_vars {
+                env::set_var(name, value);
+            }
+            continue;
+        };
+        let is_last = i == last_index;
+
+        if let Some(result) = run_builtin(&command, &segment.args[1..], path, shell) {
+            let text = match result {
+                Ok(text) => text,
+                Err(e) => {
+                    shell.last_exit_code = 1;
+                    reap_children(&mut children);
+                    return Err(e);
+                }
+            };
+            if is_last {
+                shell.last_exit_code = 0;
+            }
+            if let Some((target, append)) = &segment.stdout_redirect {
+                let mut file = open_stdout_redirect(target, *append)?;
+                file.write_all(text.as_bytes())?;
+                file.write_all(b"\n")?;
+            } else if is_last {
+                captured = text;
+            } else {
+                builtin_feed = Some(format!("{}\n", text));
+            }
+            prev_stdout = None;
+            continue;
+        }
+
+        let Some(program) = Command::get_command_path(&command, path) else {
+            shell.last_exit_code = 127;
+            reap_children(&mut children);
+            return Err(anyhow::anyhow!("{}: command not found", command));
+        };
+        let mut cmd = std::process::Command::new(program);
+        cmd.args(&segment.args[1..]);
+        for (name, value) in &segment.env_vars {
+            cmd.env(name, value);
+        }
+
+        if let Some(stdin_path) = &segment.stdin_redirect {
+            let file = File::open(stdin_path)
+                .with_context(|| format!("Failed to open {} for reading", stdin_path.display()))?;
+            cmd.stdin(Stdio::from(file));
+        } else if let Some(stdout) = prev_stdout.take() {
+            cmd.stdin(Stdio::from(stdout));
+        } else if builtin_feed.is_some() {
+            cmd.stdin(Stdio::piped());
+        } else if background {
+            // Backgrounded jobs shouldn't compete with the shell for
+            // terminal input.
+            cmd.stdin(Stdio::null());
+        } else {
+            // First stage with nothing feeding it: inherit the shell's own
+            // stdin so interactive programs (vim, less, ...) see a real TTY.
+            cmd.stdin(Stdio::inherit());
+        }
+
+        if let Some((target, append)) = &segment.stdout_redirect {
+            cmd.stdout(Stdio::from(open_stdout_redirect(target, *append)?));
+        } else if is_last {
+            // Inherit instead of buffering through `.output()`: interactive
+            // and long-running programs then show output live instead of
+            // only after they exit.
+            cmd.stdout(Stdio::inherit());
+        } else {
+            cmd.stdout(Stdio::piped());
+        }
+
+        let mut child = cmd
+            .spawn()
+            .with_context(|| format!("Failed to execute command: {}", command))?;
+
+        if let Some(text) = builtin_feed.take() {
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(text.as_bytes())?;
+            }
+        }
+
+        prev_stdout = child.stdout.take();
+        children.push((command, child));
+    }
+
+    if background {
+        if children.is_empty() {
+            return Ok(captured);
+        }
+        let id = shell.next_job_id;
+        shell.next_job_id += 1;
+        let pid = children.last().map(|(_, child)| child.id()).unwrap_or(0);
+        let children = children.into_iter().map(|(_, child)| child).collect();
+        shell.jobs.push(Job {
+            id,
+            command: label,
+            children,
+        });
+        return Ok(format!("[{}] {}", id, pid));
+    }
+
+    if let Some(mut stdout) = prev_stdout {
+        let mut buf = String::new();
+        io::Read::read_to_string(&mut stdout, &mut buf)?;
+        captured = buf.trim_end().to_string();
+    }
+
+    // Wait on every stage in order, starting with the earliest, so no stage
+    // blocks writing to a pipe nobody is draining yet. Only the last
+    // stage's status determines `$?` and whether the pipeline errors,
+    // matching ordinary shell semantics.
+    let last_child_index = children.len().saturating_sub(1);
+    for (index, (command, child)) in children.iter_mut().enumerate() {
+        let status = child.wait().context("Failed to wait on child process")?;
+        if index == last_child_index {
+            shell.last_exit_code = status.code().unwrap_or(1);
+            check_exit_status(command, status)?;
+        }
+    }
+
+    Ok(captured)
+}
+
+/// Waits on every already-spawned stage so an early `Err` return from
+/// `execute_pipeline` doesn't leave earlier, still-running stages as
+/// zombies once they exit.
+fn reap_children(children: &mut [(String, std::process::Child)]) {
+    for (_, child) in children.iter_mut() {
+        let _ = child.wait();
+    }
+}
+
+/// Formats a child's non-zero exit: the command name, the directory it ran
+/// in, and the raw exit status.
+fn command_error(command: &str, status: std::process::ExitStatus) -> String {
+    let cwd = env::current_dir()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| "?".to_string());
+    format!(
+        "Command `{}` (running in folder `{}`) exited with status {:?}",
+        command,
+        cwd,
+        status.code()
+    )
+}
+
+/// Returns an error built by `command_error` if `status` indicates failure.
+fn check_exit_status(command: &str, status: std::process::ExitStatus) -> Result<()> {
+    if status.success() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(command_error(command, status)))
+    }
+}
+
+fn parse(input: String, path: &[PathBuf], shell: &mut Shell) -> Result<String> {
+    let mut tokens = lexer::tokenize(&input, shell.last_exit_code)?;
+    let background = tokens.last().is_some_and(|token| token == "&");
+    if background {
+        tokens.pop();
+    }
+    if tokens.is_empty() {
+        return Ok(String::default());
+    }
+    let label = tokens.join(" ");
+    let segments = parse_segments(&tokens);
+    if segments
+        .iter()
+        .all(|s| s.args.is_empty() && s.env_vars.is_empty())
+    {
+        return Ok(String::default());
+    }
+    execute_pipeline(segments, path, shell, background, label)
 }
 
 fn is_executable(path: &PathBuf) -> bool {
@@ -185,3 +608,42 @@ fn is_executable(path: &PathBuf) -> bool {
         metadata.permissions().mode() & 0o111 != 0
     }).unwrap_or(false)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_simple_assignment() {
+        assert_eq!(
+            parse_assignment("FOO=bar"),
+            Some(("FOO".to_string(), "bar".to_string()))
+        );
+    }
+
+    #[test]
+    fn parses_assignment_with_underscore_and_digits_in_name() {
+        assert_eq!(
+            parse_assignment("_FOO_1=bar"),
+            Some(("_FOO_1".to_string(), "bar".to_string()))
+        );
+    }
+
+    #[test]
+    fn parses_assignment_with_empty_value() {
+        assert_eq!(
+            parse_assignment("FOO="),
+            Some(("FOO".to_string(), String::new()))
+        );
+    }
+
+    #[test]
+    fn rejects_name_starting_with_a_digit() {
+        assert_eq!(parse_assignment("1FOO=bar"), None);
+    }
+
+    #[test]
+    fn rejects_token_without_equals_sign() {
+        assert_eq!(parse_assignment("FOO"), None);
+    }
+}
@@ -0,0 +1,232 @@
+use anyhow::{Context, Result};
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+/// What the user did with the current line.
+pub enum LineResult {
+    /// Enter was pressed; this is the submitted line.
+    Submitted(String),
+    /// Ctrl-D on an empty line: the caller should exit.
+    Eof,
+}
+
+/// Raw-mode readline with history and Tab completion. History is an
+/// in-memory ring buffer capped at `history_limit` and persisted to
+/// `~/.rush_history`.
+pub struct Readline {
+    history: VecDeque<String>,
+    history_limit: usize,
+    history_path: Option<PathBuf>,
+}
+
+impl Readline {
+    pub fn new(history_limit: usize) -> Self {
+        let history_path = std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".rush_history"));
+        let mut history: VecDeque<String> = history_path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| contents.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+        while history.len() > history_limit {
+            history.pop_front();
+        }
+        Self {
+            history,
+            history_limit,
+            history_path,
+        }
+    }
+
+    fn remember(&mut self, line: &str) {
+        if line.is_empty() || self.history.back().map(String::as_str) == Some(line) {
+            return;
+        }
+        self.history.push_back(line.to_string());
+        while self.history.len() > self.history_limit {
+            self.history.pop_front();
+        }
+        if let Some(path) = &self.history_path {
+            let contents: Vec<&str> = self.history.iter().map(String::as_str).collect();
+            let _ = fs::write(path, contents.join("\n"));
+        }
+    }
+
+    /// Reads one line with full editing support. Falls back to a plain
+    /// `read_line` when stdin isn't a TTY (e.g. piped input or tests).
+    pub fn read_line(&mut self, prompt: &str, complete: impl Fn(&str) -> Vec<String>) -> Result<LineResult> {
+        if !is_tty() {
+            return self.read_line_plain(prompt);
+        }
+        let _guard = RawModeGuard::enable()?;
+        self.edit_loop(prompt, &complete)
+    }
+
+    fn read_line_plain(&mut self, prompt: &str) -> Result<LineResult> {
+        print!("{}", prompt);
+        io::stdout().flush().ok();
+        let mut input = String::new();
+        let bytes_read = io::stdin()
+            .read_line(&mut input)
+            .context("Failed reading from stdin.")?;
+        if bytes_read == 0 {
+            return Ok(LineResult::Eof);
+        }
+        let line = input.trim_end_matches('\n').to_string();
+        self.remember(&line);
+        Ok(LineResult::Submitted(line))
+    }
+
+    fn edit_loop(&mut self, prompt: &str, complete: &impl Fn(&str) -> Vec<String>) -> Result<LineResult> {
+        let mut buf: Vec<char> = Vec::new();
+        let mut cursor = 0usize;
+        let mut history_index = self.history.len();
+        let stdin = io::stdin();
+        let mut stdin = stdin.lock();
+        let mut stdout = io::stdout();
+
+        redraw(prompt, &buf, cursor, &mut stdout)?;
+
+        loop {
+            let mut byte = [0u8; 1];
+            if stdin.read(&mut byte).context("Failed reading from stdin.")? == 0 {
+                return Ok(LineResult::Eof);
+            }
+            match byte[0] {
+                b'\r' | b'\n' => {
+                    writeln!(stdout)?;
+                    let line: String = buf.into_iter().collect();
+                    self.remember(&line);
+                    return Ok(LineResult::Submitted(line));
+                }
+                0x04 if buf.is_empty() => {
+                    writeln!(stdout)?;
+                    return Ok(LineResult::Eof);
+                }
+                0x03 => {
+                    writeln!(stdout, "^C")?;
+                    buf.clear();
+                    cursor = 0;
+                    history_index = self.history.len();
+                    redraw(prompt, &buf, cursor, &mut stdout)?;
+                }
+                0x7f | 0x08 if cursor > 0 => {
+                    cursor -= 1;
+                    buf.remove(cursor);
+                    redraw(prompt, &buf, cursor, &mut stdout)?;
+                }
+                b'\t' => {
+                    let word_start = buf[..cursor]
+                        .iter()
+                        .rposition(|&c| c == ' ')
+                        .map_or(0, |i| i + 1);
+                    let prefix: String = buf[word_start..cursor].iter().collect();
+                    let candidates = complete(&prefix);
+                    match candidates.as_slice() {
+                        [] => {}
+                        [only] => {
+                            for c in only.chars().skip(prefix.chars().count()) {
+                                buf.insert(cursor, c);
+                                cursor += 1;
+                            }
+                            redraw(prompt, &buf, cursor, &mut stdout)?;
+                        }
+                        many => {
+                            writeln!(stdout)?;
+                            writeln!(stdout, "{}", many.join("  "))?;
+                            redraw(prompt, &buf, cursor, &mut stdout)?;
+                        }
+                    }
+                }
+                0x1b => {
+                    let mut seq = [0u8; 2];
+                    if stdin.read(&mut seq).context("Failed reading from stdin.")? < 2 || seq[0] != b'[' {
+                        continue;
+                    }
+                    match seq[1] {
+                        b'A' if history_index > 0 => {
+                            history_index -= 1;
+                            buf = self.history[history_index].chars().collect();
+                            cursor = buf.len();
+                            redraw(prompt, &buf, cursor, &mut stdout)?;
+                        }
+                        b'B' => {
+                            history_index = (history_index + 1).min(self.history.len());
+                            buf = self
+                                .history
+                                .get(history_index)
+                                .map(|s| s.chars().collect())
+                                .unwrap_or_default();
+                            cursor = buf.len();
+                            redraw(prompt, &buf, cursor, &mut stdout)?;
+                        }
+                        b'C' if cursor < buf.len() => {
+                            cursor += 1;
+                            redraw(prompt, &buf, cursor, &mut stdout)?;
+                        }
+                        b'D' if cursor > 0 => {
+                            cursor -= 1;
+                            redraw(prompt, &buf, cursor, &mut stdout)?;
+                        }
+                        _ => {}
+                    }
+                }
+                c if c.is_ascii_graphic() || c == b' ' => {
+                    buf.insert(cursor, c as char);
+                    cursor += 1;
+                    redraw(prompt, &buf, cursor, &mut stdout)?;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Redraws the current prompt and buffer, leaving the cursor at `cursor`.
+fn redraw(prompt: &str, buf: &[char], cursor: usize, stdout: &mut io::Stdout) -> Result<()> {
+    let line: String = buf.iter().collect();
+    write!(stdout, "\r\x1b[K{}{}", prompt, line)?;
+    let back = buf.len() - cursor;
+    if back > 0 {
+        write!(stdout, "\x1b[{}D", back)?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+fn is_tty() -> bool {
+    unsafe { libc::isatty(libc::STDIN_FILENO) == 1 }
+}
+
+/// RAII guard that puts the terminal into raw mode (no echo, no line
+/// buffering, no signal-generating control characters) and restores the
+/// original settings on drop.
+struct RawModeGuard {
+    original: libc::termios,
+}
+
+impl RawModeGuard {
+    fn enable() -> Result<Self> {
+        unsafe {
+            let mut original: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                return Err(io::Error::last_os_error()).context("tcgetattr failed");
+            }
+            let mut raw = original;
+            libc::cfmakeraw(&mut raw);
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+                return Err(io::Error::last_os_error()).context("tcsetattr failed");
+            }
+            Ok(Self { original })
+        }
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}